@@ -1,6 +1,9 @@
-use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
+use notify::Watcher;
+use rhai::module_resolvers::FileModuleResolver;
 use rhai::packages::Package;
 use rhai::Shared;
 use serde_json::Error as SerdeError;
@@ -49,37 +52,95 @@ fn build_sci_module() -> SharedModule {
     flatten_package_module(&package)
 }
 
-fn collect_module_entries(prefix: &str, module: &rhai::Module, entries: &mut BTreeSet<String>) {
-    for (name, _) in module.iter_fn() {
-        entries.insert(format!("{prefix}::{name}"));
+/// Distinguishes a namespace/module sentinel entry from a concrete function
+/// entry, so the frontend can style completions for modules (`rand::`)
+/// differently from completions for functions (`rand::rand`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionEntryKind {
+    Namespace,
+    Function,
+}
+
+/// A single completion candidate surfaced to the editor.
+///
+/// `params` and `return_type` are empty for [`CompletionEntryKind::Namespace`]
+/// entries, which carry no function metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionEntry {
+    pub qualified_name: String,
+    pub kind: CompletionEntryKind,
+    pub params: Vec<String>,
+    pub return_type: String,
+}
+
+/// Appends every function and sub-module entry reachable from `module` to
+/// `entries`, qualifying names with `prefix`.
+///
+/// Functions are keyed by their full `(qualified_name, params)` signature
+/// rather than `qualified_name` alone, so overloads (for example `rand::rand()`
+/// vs `rand::rand(min, max)`) each keep their own parameter/return-type
+/// metadata instead of the first overload shadowing the rest.
+fn collect_module_completion_entries(
+    prefix: &str,
+    module: &rhai::Module,
+    entries: &mut Vec<CompletionEntry>,
+) {
+    for func in module.iter_fn() {
+        let qualified_name = format!("{prefix}::{}", func.name);
+        let params: Vec<String> = func
+            .metadata
+            .params_info
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let entry = CompletionEntry {
+            qualified_name,
+            kind: CompletionEntryKind::Function,
+            params,
+            return_type: func.metadata.return_type.to_string(),
+        };
+        if !entries.contains(&entry) {
+            entries.push(entry);
+        }
     }
 
     for (module_name, sub_module) in module.iter_sub_modules() {
         let nested_prefix = format!("{prefix}::{module_name}");
-        entries.insert(nested_prefix.clone());
-        entries.insert(format!("{nested_prefix}::"));
-        collect_module_entries(&nested_prefix, sub_module.as_ref(), entries);
+        for qualified_name in [nested_prefix.clone(), format!("{nested_prefix}::")] {
+            let entry = CompletionEntry {
+                qualified_name,
+                kind: CompletionEntryKind::Namespace,
+                params: Vec::new(),
+                return_type: String::new(),
+            };
+            if !entries.contains(&entry) {
+                entries.push(entry);
+            }
+        }
+        collect_module_completion_entries(&nested_prefix, sub_module.as_ref(), entries);
     }
 }
 
-/// Collects completion entries for each bundled Rhai package.
+/// Collects completion entries for each bundled Rhai package, including each
+/// function's parameter count and parameter/return type names pulled from
+/// Rhai's function metadata.
 ///
-/// The returned list includes namespace identifiers (for example, `rand` and
-/// `rand::`), as well as fully-qualified function and sub-module names.
+/// The returned list includes namespace sentinels (for example, `rand` and
+/// `rand::`), as well as fully-qualified function and sub-module entries.
 ///
 /// # Examples
 ///
 /// ```
-/// # use app::collect_completion_entries;
-/// let entries = collect_completion_entries();
-/// assert!(entries.iter().any(|entry| entry == "rand"));
-/// assert!(entries.iter().any(|entry| entry == "rand::"));
-/// assert!(entries
+/// # use app::{collect_completion_entries_detailed, CompletionEntryKind};
+/// let entries = collect_completion_entries_detailed();
+/// let rand_fn = entries
 ///     .iter()
-///     .any(|entry| entry.starts_with("rand::") && entry.len() > "rand::".len()));
+///     .find(|entry| entry.qualified_name == "rand::rand" && entry.params.len() == 2)
+///     .expect("rand::rand(start, end) should be a completion entry");
+/// assert_eq!(rand_fn.kind, CompletionEntryKind::Function);
 /// ```
-pub fn collect_completion_entries() -> Vec<String> {
-    let mut entries: BTreeSet<String> = BTreeSet::new();
+pub fn collect_completion_entries_detailed() -> Vec<CompletionEntry> {
+    let mut entries: Vec<CompletionEntry> = Vec::new();
 
     let modules = vec![
         ("rand", build_rand_module()),
@@ -90,16 +151,59 @@ pub fn collect_completion_entries() -> Vec<String> {
     ];
 
     for (namespace, module) in modules {
-        entries.insert(namespace.to_string());
-        entries.insert(format!("{namespace}::"));
-        collect_module_entries(namespace, module.as_ref(), &mut entries);
+        for qualified_name in [namespace.to_string(), format!("{namespace}::")] {
+            let entry = CompletionEntry {
+                qualified_name,
+                kind: CompletionEntryKind::Namespace,
+                params: Vec::new(),
+                return_type: String::new(),
+            };
+            if !entries.contains(&entry) {
+                entries.push(entry);
+            }
+        }
+        collect_module_completion_entries(namespace, module.as_ref(), &mut entries);
     }
 
-    entries.into_iter().collect()
+    entries.sort_by(|a, b| {
+        a.qualified_name
+            .cmp(&b.qualified_name)
+            .then_with(|| a.params.cmp(&b.params))
+    });
+    entries
+}
+
+/// Collects completion entries for each bundled Rhai package.
+///
+/// The returned list includes namespace identifiers (for example, `rand` and
+/// `rand::`), as well as fully-qualified function and sub-module names. This
+/// is a compatibility shim over [`collect_completion_entries_detailed`] that
+/// flattens each entry down to its qualified name.
+///
+/// # Examples
+///
+/// ```
+/// # use app::collect_completion_entries;
+/// let entries = collect_completion_entries();
+/// assert!(entries.iter().any(|entry| entry == "rand"));
+/// assert!(entries.iter().any(|entry| entry == "rand::"));
+/// assert!(entries
+///     .iter()
+///     .any(|entry| entry.starts_with("rand::") && entry.len() > "rand::".len()));
+/// ```
+pub fn collect_completion_entries() -> Vec<String> {
+    collect_completion_entries_detailed()
+        .into_iter()
+        .map(|entry| entry.qualified_name)
+        .collect()
 }
 
 /// Registers all bundled namespaces on the provided engine and returns it.
 ///
+/// No module resolver is installed: `import` statements are handled by
+/// whatever resolver `engine` was built with. Use [`configure_engine_with_base_path`] when
+/// the script being run lives on disk and may `import` sibling `.rhai` files.
+///
 /// # Examples
 ///
 /// ```
@@ -113,15 +217,160 @@ pub fn collect_completion_entries() -> Vec<String> {
 /// # }
 /// # demo().unwrap();
 /// ```
-pub fn configure_engine(mut engine: rhai::Engine) -> rhai::Engine {
+pub fn configure_engine(engine: rhai::Engine) -> rhai::Engine {
+    configure_engine_with_base_path(engine, None::<PathBuf>)
+}
+
+/// Registers all bundled namespaces and, when `base_path` is given, enables
+/// `import "..."` of local `.rhai` files.
+///
+/// Imports are resolved relative to the directory of the script that contains
+/// the `import` statement: the entry script resolves against `base_path`, and
+/// anything *that* script imports resolves against its own parent directory,
+/// so nested imports can move their target files without the entry script
+/// knowing. Compiled modules are cached by canonicalized path, so a diamond
+/// of imports only compiles each file once.
+///
+/// # Examples
+///
+/// ```
+/// # use app::configure_engine_with_base_path;
+/// # use rhai::Engine;
+/// let engine = configure_engine_with_base_path(Engine::new(), Some(std::env::temp_dir()));
+/// let result: i64 = engine.eval("40 + 2").unwrap();
+/// assert_eq!(result, 42);
+/// ```
+pub fn configure_engine_with_base_path(
+    mut engine: rhai::Engine,
+    base_path: Option<impl AsRef<Path>>,
+) -> rhai::Engine {
     engine.register_static_module("rand", build_rand_module());
     engine.register_static_module("fs", build_fs_module());
     engine.register_static_module("url", build_url_module());
     engine.register_static_module("ml", build_ml_module());
     engine.register_static_module("sci", build_sci_module());
+
+    if let Some(base_path) = base_path {
+        engine.set_module_resolver(FileModuleResolver::new_with_path(base_path.as_ref()));
+    }
+
     engine
 }
 
+/// Default ceiling on the number of Rhai operations a script may perform
+/// before evaluation aborts, guarding the REPL against runaway loops such as
+/// `while true {}`.
+pub const DEFAULT_MAX_OPERATIONS: u64 = 5_000_000;
+
+/// Default ceiling on function-call nesting depth.
+pub const DEFAULT_MAX_CALL_LEVELS: usize = 64;
+
+/// Like [`configure_engine_with_base_path`], but also installs operation and
+/// call-nesting ceilings plus an `on_progress` hook that cooperatively aborts
+/// evaluation once `cancel` is set.
+///
+/// Pass `max_operations: Some(0)` to disable the operation ceiling entirely,
+/// for headless/test callers that intentionally run long scripts.
+/// `max_operations: None` applies [`DEFAULT_MAX_OPERATIONS`].
+///
+/// # Examples
+///
+/// ```
+/// # use std::sync::atomic::AtomicBool;
+/// # use std::sync::Arc;
+/// # use app::configure_engine_with_limits;
+/// # use rhai::Engine;
+/// let cancel = Arc::new(AtomicBool::new(false));
+/// let engine = configure_engine_with_limits(Engine::new(), None::<std::path::PathBuf>, None, cancel);
+/// let result: i64 = engine.eval("40 + 2").unwrap();
+/// assert_eq!(result, 42);
+/// ```
+pub fn configure_engine_with_limits(
+    engine: rhai::Engine,
+    base_path: Option<impl AsRef<Path>>,
+    max_operations: Option<u64>,
+    cancel: Arc<AtomicBool>,
+) -> rhai::Engine {
+    let mut engine = configure_engine_with_base_path(engine, base_path);
+
+    engine.set_max_operations(max_operations.unwrap_or(DEFAULT_MAX_OPERATIONS));
+    engine.set_max_call_levels(DEFAULT_MAX_CALL_LEVELS);
+    engine.on_progress(move |_ops| {
+        cancel
+            .load(Ordering::Relaxed)
+            .then(|| rhai::Dynamic::from("execution cancelled"))
+    });
+
+    engine
+}
+
+/// Renders a caret-annotated diagnostic for an error at `position` within
+/// `script`, similar to how modern script hosts render their errors.
+///
+/// The diagnostic is three lines: the offending source line, a caret `^`
+/// padded out to the error's column, and the human-readable `message`. When
+/// `position` carries no line/column information (as with some runtime
+/// errors), `message` is returned unadorned.
+///
+/// # Examples
+///
+/// ```
+/// # use app::render_diagnostic;
+/// # use rhai::Position;
+/// let script = "let x = 1;\nlet y = !;";
+/// let diagnostic = render_diagnostic(script, "Unexpected '!'", Position::new(2, 9));
+/// assert_eq!(diagnostic, "let y = !;\n        ^\nUnexpected '!'");
+/// ```
+pub fn render_diagnostic(script: &str, message: &str, position: rhai::Position) -> String {
+    match (position.line(), position.position()) {
+        (Some(line_number), Some(column)) if column > 0 => {
+            match script.lines().nth(line_number - 1) {
+                Some(source_line) => {
+                    let caret = format!("{}^", " ".repeat(column - 1));
+                    format!("{source_line}\n{caret}\n{message}")
+                }
+                None => message.to_string(),
+            }
+        }
+        _ => message.to_string(),
+    }
+}
+
+/// Renders a [`rhai::ParseError`] (as produced by `Engine::compile`) as a
+/// caret-annotated diagnostic against the original `script` text.
+///
+/// # Examples
+///
+/// ```
+/// # use app::render_parse_error;
+/// # use rhai::Engine;
+/// let script = "let x = ;";
+/// let error = Engine::new().compile(script).unwrap_err();
+/// assert!(render_parse_error(script, &error).contains('^'));
+/// ```
+pub fn render_parse_error(script: &str, error: &rhai::ParseError) -> String {
+    render_diagnostic(script, &error.to_string(), error.position())
+}
+
+/// Renders an [`rhai::EvalAltResult`] as a caret-annotated diagnostic against
+/// the original `script` text.
+///
+/// # Examples
+///
+/// ```
+/// # use app::render_eval_error;
+/// # use rhai::Engine;
+/// let script = "throw(\"boom\");";
+/// let error = Engine::new().eval::<()>(script).unwrap_err();
+/// assert!(render_eval_error(script, &error).contains("boom"));
+/// ```
+pub fn render_eval_error(script: &str, error: &rhai::EvalAltResult) -> String {
+    if matches!(error, rhai::EvalAltResult::ErrorTerminated(..)) {
+        return "execution cancelled".to_string();
+    }
+    render_diagnostic(script, &error.to_string(), error.position())
+}
+
 pub type OutputSink = Arc<dyn Fn(String) + Send + Sync + 'static>;
 
 /// Builds a JavaScript snippet that safely forwards a message to the frontend.
@@ -167,17 +416,446 @@ pub fn run_rhai_script_with_sink(script: &str, sink: &OutputSink) {
     match engine.compile(script) {
         Ok(script_ast) => match engine.eval_ast::<rhai::Dynamic>(&script_ast) {
             Ok(result) => sink(result.to_string()),
-            Err(e) => sink(format!("{:?}", e)),
+            Err(e) => sink(render_eval_error(script, &e)),
+        },
+        Err(e) => sink(render_parse_error(script, &e)),
+    }
+}
+
+/// Compiles and evaluates the `.rhai` script at `path`, streaming output and
+/// errors into `sink`.
+///
+/// Unlike [`run_rhai_script_with_sink`], the engine is configured with a
+/// module resolver rooted at `path`'s parent directory, so `import "..."`
+/// statements in the script (and in anything it imports) resolve relative to
+/// the file on disk rather than the process's working directory.
+///
+/// A failure to read `path` is reported through `sink` rather than returned,
+/// matching the other error sinks in this module.
+///
+/// # Examples
+///
+/// ```
+/// # use std::sync::{Arc, Mutex};
+/// # use app::{run_rhai_script_file_with_sink, OutputSink};
+/// let dir = std::env::temp_dir().join(format!("pastrami-doctest-{}", std::process::id()));
+/// std::fs::create_dir_all(&dir).unwrap();
+/// let script_path = dir.join("entry.rhai");
+/// std::fs::write(&script_path, "40 + 2").unwrap();
+///
+/// let captured = Arc::new(Mutex::new(Vec::new()));
+/// let sink_target = Arc::clone(&captured);
+/// let sink: OutputSink = Arc::new(move |message| {
+///     sink_target.lock().unwrap().push(message);
+/// });
+/// run_rhai_script_file_with_sink(&script_path, &sink);
+/// assert_eq!(captured.lock().unwrap().last().unwrap(), "42");
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub fn run_rhai_script_file_with_sink(path: &Path, sink: &OutputSink) {
+    let script = match std::fs::read_to_string(path) {
+        Ok(script) => script,
+        Err(e) => {
+            sink(format!("failed to read {}: {e}", path.display()));
+            return;
+        }
+    };
+
+    let base_path = path.parent().map(Path::to_path_buf);
+    let mut engine = configure_engine_with_base_path(rhai::Engine::new(), base_path);
+
+    let print_sink = Arc::clone(sink);
+    engine.on_print(move |x| {
+        print_sink(x.to_string());
+    });
+
+    match engine.compile(&script) {
+        Ok(script_ast) => match engine.eval_ast::<rhai::Dynamic>(&script_ast) {
+            Ok(result) => sink(result.to_string()),
+            Err(e) => sink(render_eval_error(&script, &e)),
+        },
+        Err(e) => sink(render_parse_error(&script, &e)),
+    }
+}
+
+/// Like [`run_rhai_script_file_with_sink`], but built with
+/// [`configure_engine_with_limits`] so a runaway script is bounded by an
+/// operation ceiling and can be stopped cooperatively by setting `cancel`.
+/// Used by [`watch_rhai_file`] so a watched script that hangs doesn't hang the
+/// watch loop with it.
+///
+/// # Examples
+///
+/// ```
+/// # use std::sync::atomic::AtomicBool;
+/// # use std::sync::{Arc, Mutex};
+/// # use app::{run_rhai_script_file_with_sink_cancellable, OutputSink};
+/// let dir = std::env::temp_dir().join(format!("pastrami-doctest-cancellable-{}", std::process::id()));
+/// std::fs::create_dir_all(&dir).unwrap();
+/// let script_path = dir.join("runaway.rhai");
+/// std::fs::write(&script_path, "while true { }").unwrap();
+///
+/// let captured = Arc::new(Mutex::new(Vec::new()));
+/// let sink_target = Arc::clone(&captured);
+/// let sink: OutputSink = Arc::new(move |message| {
+///     sink_target.lock().unwrap().push(message);
+/// });
+/// run_rhai_script_file_with_sink_cancellable(
+///     &script_path,
+///     &sink,
+///     Arc::new(AtomicBool::new(false)),
+///     Some(1_000),
+/// );
+/// assert!(captured.lock().unwrap().last().unwrap().to_lowercase().contains("operation"));
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub fn run_rhai_script_file_with_sink_cancellable(
+    path: &Path,
+    sink: &OutputSink,
+    cancel: Arc<AtomicBool>,
+    max_operations: Option<u64>,
+) {
+    let script = match std::fs::read_to_string(path) {
+        Ok(script) => script,
+        Err(e) => {
+            sink(format!("failed to read {}: {e}", path.display()));
+            return;
+        }
+    };
+
+    let base_path = path.parent().map(Path::to_path_buf);
+    let mut engine =
+        configure_engine_with_limits(rhai::Engine::new(), base_path, max_operations, cancel);
+
+    let print_sink = Arc::clone(sink);
+    engine.on_print(move |x| {
+        print_sink(x.to_string());
+    });
+
+    match engine.compile(&script) {
+        Ok(script_ast) => match engine.eval_ast::<rhai::Dynamic>(&script_ast) {
+            Ok(result) => sink(result.to_string()),
+            Err(e) => sink(render_eval_error(&script, &e)),
+        },
+        Err(e) => sink(render_parse_error(&script, &e)),
+    }
+}
+
+/// How long to wait for further filesystem events before treating a burst of
+/// changes as a single edit; coalesces the write-then-truncate pattern some
+/// editors use when saving.
+const WATCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(150);
+
+fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Handle to a running [`watch_rhai_file`] loop.
+///
+/// Dropping the handle stops the filesystem watcher and lets the background
+/// rerun loop exit; calling [`WatchHandle::stop`] makes the loop exit at its
+/// next debounce tick, while the watcher itself lives until the handle is
+/// dropped.
+pub struct WatchHandle {
+    stop: Arc<AtomicBool>,
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl WatchHandle {
+    /// Stops watching; the underlying file stops being re-run on change.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Runs the `.rhai` script at `path` once via
+/// [`run_rhai_script_file_with_sink_cancellable`], then re-runs it on every
+/// subsequent filesystem change, emitting a `--- rerun at <unix timestamp> ---`
+/// separator before each rerun.
+///
+/// Rapid successive change events are coalesced within [`WATCH_DEBOUNCE`] so
+/// editors that write-then-truncate don't trigger a double run. Failures to
+/// read the file on rerun are reported through `sink` rather than panicking.
+/// Each run (initial and rerun) is bounded by `max_operations` and can be
+/// stopped cooperatively by setting `cancel`, so a watched script that hangs
+/// doesn't hang the watch loop with it.
+///
+/// # Errors
+/// Returns an error if the underlying filesystem watcher cannot be created or
+/// attached to `path`.
+pub fn watch_rhai_file(
+    path: PathBuf,
+    sink: OutputSink,
+    cancel: Arc<AtomicBool>,
+    max_operations: Option<u64>,
+) -> notify::Result<WatchHandle> {
+    run_rhai_script_file_with_sink_cancellable(&path, &sink, Arc::clone(&cancel), max_operations);
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let _ = tx.send(event);
+    })?;
+    watcher.watch(&path, notify::RecursiveMode::NonRecursive)?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let loop_stop = Arc::clone(&stop);
+
+    std::thread::spawn(move || {
+        let mut pending = false;
+
+        loop {
+            if loop_stop.load(Ordering::Relaxed) {
+                return;
+            }
+
+            match rx.recv_timeout(WATCH_DEBOUNCE) {
+                Ok(Ok(_event)) => pending = true,
+                Ok(Err(e)) => sink(format!("watch error: {e}")),
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                    if pending {
+                        pending = false;
+                        sink(format!("--- rerun at {} ---", unix_timestamp()));
+                        run_rhai_script_file_with_sink_cancellable(
+                            &path,
+                            &sink,
+                            Arc::clone(&cancel),
+                            max_operations,
+                        );
+                    }
+                }
+                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    });
+
+    Ok(WatchHandle {
+        stop,
+        _watcher: watcher,
+    })
+}
+
+/// Compiles and evaluates `script`, streaming output and errors into `sink`,
+/// under the operation/call-depth guards and cooperative cancellation from
+/// [`configure_engine_with_limits`].
+///
+/// `cancel` is checked on every progress tick; setting it from another thread
+/// aborts the running script, which is reported to `sink` as "execution
+/// cancelled". Pass `max_operations: Some(0)` to disable the operation
+/// ceiling for scripts that are expected to run long.
+///
+/// # Examples
+///
+/// ```
+/// # use std::sync::atomic::AtomicBool;
+/// # use std::sync::{Arc, Mutex};
+/// # use app::{run_rhai_script_with_sink_cancellable, OutputSink};
+/// let cancel = Arc::new(AtomicBool::new(true));
+/// let captured = Arc::new(Mutex::new(Vec::new()));
+/// let sink_target = Arc::clone(&captured);
+/// let sink: OutputSink = Arc::new(move |message| {
+///     sink_target.lock().unwrap().push(message);
+/// });
+///
+/// run_rhai_script_with_sink_cancellable("while true { }", &sink, cancel, Some(0));
+/// assert_eq!(captured.lock().unwrap().last().unwrap(), "execution cancelled");
+/// ```
+pub fn run_rhai_script_with_sink_cancellable(
+    script: &str,
+    sink: &OutputSink,
+    cancel: Arc<AtomicBool>,
+    max_operations: Option<u64>,
+) {
+    let mut engine =
+        configure_engine_with_limits(rhai::Engine::new(), None::<PathBuf>, max_operations, cancel);
+
+    let print_sink = Arc::clone(sink);
+    engine.on_print(move |x| {
+        print_sink(x.to_string());
+    });
+
+    match engine.compile(script) {
+        Ok(script_ast) => match engine.eval_ast::<rhai::Dynamic>(&script_ast) {
+            Ok(result) => sink(result.to_string()),
+            Err(e) => sink(render_eval_error(script, &e)),
         },
-        Err(e) => sink(e.to_string()),
+        Err(e) => sink(render_parse_error(script, &e)),
     }
 }
 
+/// Compiles `script`, runs every zero-parameter top-level function whose name
+/// starts with `test_`, and streams a PASS/FAIL line per test plus a final
+/// summary count to `sink`.
+///
+/// A test fails if calling it throws, per Rhai's throw-any-value semantics;
+/// the thrown value's string form is included in its FAIL line. Each test
+/// runs against its own fresh `Scope` so one test's variables can't leak into
+/// the next.
+///
+/// # Examples
+///
+/// ```
+/// # use std::sync::{Arc, Mutex};
+/// # use app::{run_rhai_tests_with_sink, OutputSink};
+/// let captured = Arc::new(Mutex::new(Vec::new()));
+/// let sink_target = Arc::clone(&captured);
+/// let sink: OutputSink = Arc::new(move |message| {
+///     sink_target.lock().unwrap().push(message);
+/// });
+///
+/// run_rhai_tests_with_sink(
+///     r#"
+///     fn test_addition() { if 1 + 1 != 2 { throw "math is broken"; } }
+///     fn test_always_fails() { throw "nope"; }
+///     "#,
+///     &sink,
+/// );
+///
+/// let output = captured.lock().unwrap();
+/// assert!(output.iter().any(|line| line == "PASS test_addition"));
+/// assert!(output.iter().any(|line| line.starts_with("FAIL test_always_fails")));
+/// assert_eq!(output.last().unwrap(), "1 passed, 1 failed, 2 total");
+/// ```
+pub fn run_rhai_tests_with_sink(script: &str, sink: &OutputSink) {
+    let mut engine = configure_engine(rhai::Engine::new());
+
+    let print_sink = Arc::clone(sink);
+    engine.on_print(move |x| {
+        print_sink(x.to_string());
+    });
+
+    let ast = match engine.compile(script) {
+        Ok(ast) => ast,
+        Err(e) => {
+            sink(render_parse_error(script, &e));
+            return;
+        }
+    };
+
+    let test_names: Vec<String> = ast
+        .iter_functions()
+        .filter(|function| function.name.starts_with("test_") && function.params.is_empty())
+        .map(|function| function.name.to_string())
+        .collect();
+
+    let mut passed = 0usize;
+    let mut failed = 0usize;
+
+    for name in &test_names {
+        let mut scope = rhai::Scope::new();
+        match engine.call_fn::<rhai::Dynamic>(&mut scope, &ast, name, ()) {
+            Ok(_) => {
+                passed += 1;
+                sink(format!("PASS {name}"));
+            }
+            Err(e) => {
+                failed += 1;
+                sink(format!("FAIL {name}: {e}"));
+            }
+        }
+    }
+
+    sink(format!(
+        "{passed} passed, {failed} failed, {} total",
+        test_names.len()
+    ));
+}
+
+/// Like [`run_rhai_tests_with_sink`], but built with
+/// [`configure_engine_with_limits`] so a runaway `test_` function is bounded
+/// by an operation ceiling and can be stopped cooperatively by setting
+/// `cancel`, the same guards [`run_rhai_script_with_sink_cancellable`] applies
+/// to a script.
+///
+/// # Examples
+///
+/// ```
+/// # use std::sync::atomic::AtomicBool;
+/// # use std::sync::{Arc, Mutex};
+/// # use app::{run_rhai_tests_with_sink_cancellable, OutputSink};
+/// let cancel = Arc::new(AtomicBool::new(false));
+/// let captured = Arc::new(Mutex::new(Vec::new()));
+/// let sink_target = Arc::clone(&captured);
+/// let sink: OutputSink = Arc::new(move |message| {
+///     sink_target.lock().unwrap().push(message);
+/// });
+///
+/// run_rhai_tests_with_sink_cancellable(
+///     r#"fn test_runs_forever() { while true { } }"#,
+///     &sink,
+///     cancel,
+///     Some(1_000),
+/// );
+///
+/// let output = captured.lock().unwrap();
+/// assert!(output
+///     .iter()
+///     .any(|line| line.starts_with("FAIL test_runs_forever")));
+/// ```
+pub fn run_rhai_tests_with_sink_cancellable(
+    script: &str,
+    sink: &OutputSink,
+    cancel: Arc<AtomicBool>,
+    max_operations: Option<u64>,
+) {
+    let mut engine =
+        configure_engine_with_limits(rhai::Engine::new(), None::<PathBuf>, max_operations, cancel);
+
+    let print_sink = Arc::clone(sink);
+    engine.on_print(move |x| {
+        print_sink(x.to_string());
+    });
+
+    let ast = match engine.compile(script) {
+        Ok(ast) => ast,
+        Err(e) => {
+            sink(render_parse_error(script, &e));
+            return;
+        }
+    };
+
+    let test_names: Vec<String> = ast
+        .iter_functions()
+        .filter(|function| function.name.starts_with("test_") && function.params.is_empty())
+        .map(|function| function.name.to_string())
+        .collect();
+
+    let mut passed = 0usize;
+    let mut failed = 0usize;
+
+    for name in &test_names {
+        let mut scope = rhai::Scope::new();
+        match engine.call_fn::<rhai::Dynamic>(&mut scope, &ast, name, ()) {
+            Ok(_) => {
+                passed += 1;
+                sink(format!("PASS {name}"));
+            }
+            Err(e) => {
+                failed += 1;
+                sink(format!("FAIL {name}: {e}"));
+            }
+        }
+    }
+
+    sink(format!(
+        "{passed} passed, {failed} failed, {} total",
+        test_names.len()
+    ));
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        append_output_script, collect_completion_entries, run_rhai_script_with_sink, OutputSink,
+        append_output_script, collect_completion_entries, collect_completion_entries_detailed,
+        collect_module_completion_entries, render_diagnostic, run_rhai_script_file_with_sink,
+        run_rhai_script_file_with_sink_cancellable, run_rhai_script_with_sink,
+        run_rhai_script_with_sink_cancellable, run_rhai_tests_with_sink,
+        run_rhai_tests_with_sink_cancellable, watch_rhai_file, CompletionEntry,
+        CompletionEntryKind, OutputSink,
     };
+    use std::sync::atomic::AtomicBool;
     use std::sync::{Arc, Mutex};
 
     fn run_script_with_collector(script: &str) -> Vec<String> {
@@ -264,6 +942,174 @@ mod tests {
         );
     }
 
+    #[test]
+    fn render_diagnostic_falls_back_to_plain_message_without_a_position() {
+        let rendered = render_diagnostic("let x = 1;", "some error", rhai::Position::NONE);
+        assert_eq!(rendered, "some error");
+    }
+
+    #[test]
+    fn render_diagnostic_underlines_the_offending_column() {
+        let script = "let x = 1;\nlet y = !;";
+        let rendered = render_diagnostic(script, "Unexpected '!'", rhai::Position::new(2, 9));
+        assert_eq!(rendered, "let y = !;\n        ^\nUnexpected '!'");
+    }
+
+    #[test]
+    fn invalid_script_reports_a_caret_annotated_diagnostic() {
+        let output = run_script_with_collector("let x = ;");
+        let last_message = output
+            .last()
+            .expect("missing output entry for invalid script");
+
+        assert!(
+            last_message.contains('^'),
+            "expected a caret-annotated diagnostic, got: {last_message}",
+        );
+    }
+
+    #[test]
+    fn cancelling_mid_run_reports_execution_cancelled() {
+        let cancel = Arc::new(AtomicBool::new(true));
+        let captured_output: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let sink_target = Arc::clone(&captured_output);
+        let sink: OutputSink = Arc::new(move |message: String| {
+            sink_target
+                .lock()
+                .expect("collector mutex poisoned")
+                .push(message);
+        });
+
+        run_rhai_script_with_sink_cancellable("while true { }", &sink, cancel, Some(0));
+
+        let output = captured_output.lock().expect("collector mutex poisoned");
+        assert_eq!(
+            output
+                .last()
+                .expect("missing output entry for cancelled script"),
+            "execution cancelled",
+        );
+    }
+
+    #[test]
+    fn exceeding_the_operation_ceiling_aborts_evaluation() {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let captured_output: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let sink_target = Arc::clone(&captured_output);
+        let sink: OutputSink = Arc::new(move |message: String| {
+            sink_target
+                .lock()
+                .expect("collector mutex poisoned")
+                .push(message);
+        });
+
+        run_rhai_script_with_sink_cancellable("while true { }", &sink, cancel, Some(1_000));
+
+        let output = captured_output.lock().expect("collector mutex poisoned");
+        let last_message = output
+            .last()
+            .expect("missing output entry for runaway script");
+        assert!(
+            last_message.to_lowercase().contains("operation"),
+            "expected an operation-limit error, got: {last_message}",
+        );
+    }
+
+    #[test]
+    fn file_scripts_can_import_sibling_modules_relative_to_their_own_directory() {
+        let dir = std::env::temp_dir().join(format!(
+            "pastrami-import-test-{}-{}",
+            std::process::id(),
+            line!()
+        ));
+        std::fs::create_dir_all(dir.join("nested")).expect("failed to create test directory");
+
+        std::fs::write(
+            dir.join("entry.rhai"),
+            r#"import "nested/utils" as u; u::answer()"#,
+        )
+        .expect("failed to write entry script");
+        std::fs::write(
+            dir.join("nested").join("utils.rhai"),
+            r#"import "helper" as h; fn answer() { h::value() }"#,
+        )
+        .expect("failed to write nested module");
+        std::fs::write(dir.join("nested").join("helper.rhai"), "fn value() { 42 }")
+            .expect("failed to write nested module's own import");
+
+        let output = {
+            let captured_output: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+            let sink_target = Arc::clone(&captured_output);
+            let sink: OutputSink = Arc::new(move |message: String| {
+                sink_target
+                    .lock()
+                    .expect("collector mutex poisoned")
+                    .push(message);
+            });
+            run_rhai_script_file_with_sink(&dir.join("entry.rhai"), &sink);
+            captured_output
+                .lock()
+                .expect("collector mutex poisoned")
+                .clone()
+        };
+
+        std::fs::remove_dir_all(&dir).expect("failed to clean up test directory");
+
+        assert_eq!(
+            output.last().expect("missing output entry for import test"),
+            "42",
+            "expected a nested import resolved relative to its own parent file, got {output:?}",
+        );
+    }
+
+    #[test]
+    fn run_rhai_tests_with_sink_reports_pass_fail_and_summary() {
+        let output = {
+            let captured_output: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+            let sink_target = Arc::clone(&captured_output);
+            let sink: OutputSink = Arc::new(move |message: String| {
+                sink_target
+                    .lock()
+                    .expect("collector mutex poisoned")
+                    .push(message);
+            });
+
+            run_rhai_tests_with_sink(
+                r#"
+                fn test_addition() { if 1 + 1 != 2 { throw "math is broken"; } }
+                fn test_always_fails() { throw "nope"; }
+                fn not_a_test() { throw "should never run"; }
+                "#,
+                &sink,
+            );
+
+            captured_output
+                .lock()
+                .expect("collector mutex poisoned")
+                .clone()
+        };
+
+        assert!(
+            output.contains(&"PASS test_addition".to_string()),
+            "expected a passing test to be reported, got {output:?}",
+        );
+        assert!(
+            output
+                .iter()
+                .any(|line| line.starts_with("FAIL test_always_fails") && line.contains("nope")),
+            "expected a failing test to report its thrown payload, got {output:?}",
+        );
+        assert!(
+            !output.iter().any(|line| line.contains("not_a_test")),
+            "expected non test_-prefixed functions to be skipped, got {output:?}",
+        );
+        assert_eq!(
+            output.last().unwrap(),
+            "1 passed, 1 failed, 2 total",
+            "expected a final summary line, got {output:?}",
+        );
+    }
+
     #[test]
     fn bundled_modules_are_available_under_namespaces() {
         let output = run_script_with_collector(
@@ -293,4 +1139,158 @@ mod tests {
             .iter()
             .any(|entry| entry.starts_with("rand::") && entry.len() > "rand::".len()));
     }
+
+    #[test]
+    fn detailed_completion_entries_distinguish_namespaces_from_functions() {
+        let entries = collect_completion_entries_detailed();
+
+        let namespace_entry = entries
+            .iter()
+            .find(|entry| entry.qualified_name == "rand")
+            .expect("missing rand namespace entry");
+        assert_eq!(namespace_entry.kind, CompletionEntryKind::Namespace);
+        assert!(namespace_entry.params.is_empty());
+
+        let function_entry = entries
+            .iter()
+            .find(|entry| entry.qualified_name == "rand::rand")
+            .expect("missing rand::rand function entry");
+        assert_eq!(function_entry.kind, CompletionEntryKind::Function);
+    }
+
+    #[test]
+    fn watch_rhai_file_reruns_on_change() {
+        let dir = std::env::temp_dir().join(format!(
+            "pastrami-watch-test-{}-{}",
+            std::process::id(),
+            line!()
+        ));
+        std::fs::create_dir_all(&dir).expect("failed to create test directory");
+        let script_path = dir.join("watched.rhai");
+        std::fs::write(&script_path, "1").expect("failed to write initial script");
+
+        let captured_output: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let sink_target = Arc::clone(&captured_output);
+        let sink: OutputSink = Arc::new(move |message: String| {
+            sink_target
+                .lock()
+                .expect("collector mutex poisoned")
+                .push(message);
+        });
+
+        let handle = watch_rhai_file(
+            script_path.clone(),
+            sink,
+            Arc::new(AtomicBool::new(false)),
+            None,
+        )
+        .expect("failed to start file watch");
+
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        std::fs::write(&script_path, "2").expect("failed to update watched script");
+
+        let saw_rerun = (0..20).any(|_| {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            let output = captured_output.lock().expect("collector mutex poisoned");
+            output.iter().any(|line| line.starts_with("--- rerun at"))
+                && output.iter().any(|line| line == "2")
+        });
+
+        handle.stop();
+        std::fs::remove_dir_all(&dir).expect("failed to clean up test directory");
+
+        assert!(
+            saw_rerun,
+            "expected the watched script to re-run after a change, got {:?}",
+            captured_output.lock().expect("collector mutex poisoned")
+        );
+    }
+
+    #[test]
+    fn test_runner_operation_ceiling_aborts_a_runaway_test() {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let captured_output: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let sink_target = Arc::clone(&captured_output);
+        let sink: OutputSink = Arc::new(move |message: String| {
+            sink_target
+                .lock()
+                .expect("collector mutex poisoned")
+                .push(message);
+        });
+
+        run_rhai_tests_with_sink_cancellable(
+            r#"fn test_runs_forever() { while true { } }"#,
+            &sink,
+            cancel,
+            Some(1_000),
+        );
+
+        let output = captured_output.lock().expect("collector mutex poisoned");
+        assert!(
+            output
+                .iter()
+                .any(|line| line.starts_with("FAIL test_runs_forever")),
+            "expected the runaway test to fail once the operation ceiling aborts it, got {output:?}",
+        );
+    }
+
+    #[test]
+    fn file_runner_operation_ceiling_aborts_a_runaway_script() {
+        let dir = std::env::temp_dir().join(format!(
+            "pastrami-file-ceiling-test-{}-{}",
+            std::process::id(),
+            line!()
+        ));
+        std::fs::create_dir_all(&dir).expect("failed to create test directory");
+        let script_path = dir.join("runaway.rhai");
+        std::fs::write(&script_path, "while true { }").expect("failed to write runaway script");
+
+        let captured_output: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let sink_target = Arc::clone(&captured_output);
+        let sink: OutputSink = Arc::new(move |message: String| {
+            sink_target
+                .lock()
+                .expect("collector mutex poisoned")
+                .push(message);
+        });
+
+        run_rhai_script_file_with_sink_cancellable(
+            &script_path,
+            &sink,
+            Arc::new(AtomicBool::new(false)),
+            Some(1_000),
+        );
+
+        std::fs::remove_dir_all(&dir).expect("failed to clean up test directory");
+
+        let output = captured_output.lock().expect("collector mutex poisoned");
+        let last_message = output
+            .last()
+            .expect("missing output entry for runaway file script");
+        assert!(
+            last_message.to_lowercase().contains("operation"),
+            "expected an operation-limit error, got: {last_message}",
+        );
+    }
+
+    #[test]
+    fn completion_entries_preserve_overloaded_function_signatures() {
+        let mut module = rhai::Module::new();
+        module.set_native_fn("overloaded", || Ok(1_i64));
+        module.set_native_fn("overloaded", |x: i64| Ok(x));
+
+        let mut entries: Vec<CompletionEntry> = Vec::new();
+        collect_module_completion_entries("ns", &module, &mut entries);
+
+        let overloaded_entries: Vec<&CompletionEntry> = entries
+            .iter()
+            .filter(|entry| entry.qualified_name == "ns::overloaded")
+            .collect();
+
+        assert_eq!(
+            overloaded_entries.len(),
+            2,
+            "expected both overloads of `overloaded` to keep their own signature, got {entries:?}",
+        );
+    }
 }
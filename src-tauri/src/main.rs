@@ -2,31 +2,17 @@
     all(not(debug_assertions), target_os = "windows"),
     windows_subsystem = "windows"
 )]
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
-use rhai::packages::Package;
-use rhai_fs::FilesystemPackage;
-use rhai_ml::MLPackage;
-use rhai_rand::RandomPackage;
-use rhai_sci::SciPackage;
-use rhai_url::UrlPackage;
+use app::{
+    append_output_script, configure_engine_with_limits, render_eval_error,
+    run_rhai_tests_with_sink_cancellable, watch_rhai_file, OutputSink, WatchHandle,
+};
 use tauri::Manager;
 
-/// Builds a JavaScript snippet that safely forwards a message to the frontend.
-///
-/// The message is serialized using `serde_json` so that newline characters,
-/// quotes, and other special characters remain valid once evaluated in the
-/// webview.
-///
-/// # Examples
-/// ```rust,ignore
-/// let script = append_output_script("Hello\nworld").unwrap();
-/// assert_eq!(script, "append_output(\"Hello\\nworld\")");
-/// ```
-fn append_output_script(message: &str) -> Result<String, serde_json::Error> {
-    serde_json::to_string(message).map(|escaped| format!("append_output({escaped})"))
-}
-
 fn send_output(window: &tauri::Window, message: &str) {
     match append_output_script(message) {
         Ok(script) => {
@@ -40,250 +26,233 @@ fn send_output(window: &tauri::Window, message: &str) {
     }
 }
 
-type SharedModule = rhai::Shared<rhai::Module>;
-
-fn flatten_package_module<P>(package: P) -> SharedModule
-where
-    P: Package,
-{
-    let shared = package.as_shared_module();
-    let mut module = rhai::Module::new();
-    module.combine_flatten((*shared).clone());
-    module.into()
-}
-
-fn build_rand_module() -> SharedModule {
-    flatten_package_module(RandomPackage::new())
-}
-
-fn build_fs_module() -> SharedModule {
-    flatten_package_module(FilesystemPackage::new())
-}
-
-fn build_url_module() -> SharedModule {
-    flatten_package_module(UrlPackage::new())
-}
-
-fn build_ml_module() -> SharedModule {
-    flatten_package_module(MLPackage::new())
-}
-
-fn build_sci_module() -> SharedModule {
-    flatten_package_module(SciPackage::new())
-}
-
-fn configure_engine(mut engine: rhai::Engine) -> rhai::Engine {
-    engine.register_static_module("rand", build_rand_module());
-    engine.register_static_module("fs", build_fs_module());
-    engine.register_static_module("url", build_url_module());
-    engine.register_static_module("ml", build_ml_module());
-    engine.register_static_module("sci", build_sci_module());
-    engine
-}
-
 struct MyState {
     engine: Mutex<rhai::Engine>,
     scope: Mutex<rhai::Scope<'static>>,
+    base_path: Mutex<Option<PathBuf>>,
+    cancel: Arc<AtomicBool>,
+    watches: Mutex<HashMap<String, WatchHandle>>,
 }
 
 fn main() {
-    let engine = configure_engine(rhai::Engine::new());
+    let cancel = Arc::new(AtomicBool::new(false));
+    let engine = configure_engine_with_limits(
+        rhai::Engine::new(),
+        None::<PathBuf>,
+        None,
+        Arc::clone(&cancel),
+    );
     let scope = rhai::Scope::new();
 
     let app_state = Arc::new(MyState {
         engine: Mutex::new(engine),
         scope: Mutex::new(scope),
+        base_path: Mutex::new(None),
+        cancel,
+        watches: Mutex::new(HashMap::new()),
     });
 
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![rhai_repl, rhai_script])
+        .invoke_handler(tauri::generate_handler![
+            rhai_repl,
+            rhai_script,
+            rhai_set_base_path,
+            rhai_run_tests,
+            rhai_reset,
+            rhai_list_vars,
+            rhai_cancel,
+            rhai_watch,
+            rhai_unwatch
+        ])
         .manage(app_state)
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
+/// Sets the directory that `import "..."` statements are resolved against for
+/// both `rhai_script` and `rhai_repl`, typically the folder containing the
+/// file the user has open in the editor. Pass an empty `path` to clear it.
 #[tauri::command]
-fn rhai_script(script: &str, window: tauri::Window) {
+fn rhai_set_base_path(path: &str, window: tauri::Window) {
     let app_state = {
         let state = window.state::<Arc<MyState>>();
         Arc::clone(&state)
     };
 
-    let sink_window = window;
-    let output_sink: OutputSink = Arc::new(move |message: String| {
-        send_output(&sink_window, &message);
-    });
+    let base_path = if path.is_empty() {
+        None
+    } else {
+        Some(PathBuf::from(path))
+    };
 
-    run_rhai_script_with_sink(script, &output_sink);
+    let engine = configure_engine_with_limits(
+        rhai::Engine::new(),
+        base_path.clone(),
+        None,
+        Arc::clone(&app_state.cancel),
+    );
+    *app_state.engine.lock().unwrap() = engine;
+    *app_state.base_path.lock().unwrap() = base_path;
 }
 
-#[tauri::command]
-fn rhai_repl(script: &str, window: tauri::Window) {
-    let app_state = {
-        let app_handle = window.app_handle();
-        let state = app_handle.state::<Arc<MyState>>();
-        Arc::clone(&state)
-    };
+/// Evaluates `script` against the managed engine and scope in `app_state`, so
+/// variables it defines are visible to later `rhai_script`/`rhai_repl` calls.
+fn eval_in_managed_scope(script: &str, window: &tauri::Window, app_state: &MyState) {
+    // Clear any stale cancel request (e.g. a `rhai_cancel` that fired while
+    // nothing was running) before this run's first progress check can see it.
+    app_state.cancel.store(false, Ordering::Relaxed);
 
     let mut engine = app_state.engine.lock().unwrap();
     let mut scope = app_state.scope.lock().unwrap();
 
-    let evaluation_window = window.clone();
-    let print_window = window;
+    let print_window = window.clone();
     engine.on_print(move |message| {
         send_output(&print_window, message);
     });
 
-    match engine.eval_with_scope::<rhai::Dynamic>(&mut scope, script) {
-        Ok(result) => send_output(&evaluation_window, &result.to_string()),
-        Err(e) => send_output(&evaluation_window, &format!("{e:?}")),
+    let result = engine.eval_with_scope::<rhai::Dynamic>(&mut scope, script);
+
+    match result {
+        Ok(result) => send_output(window, &result.to_string()),
+        Err(e) => send_output(window, &render_eval_error(script, &e)),
     }
 }
 
-type OutputSink = Arc<dyn Fn(String) + Send + Sync + 'static>;
-
-/// Executes a Rhai script and forwards every emitted message to the provided
-/// sink.
-///
-/// Messages include anything produced by the script via `print` as well as the
-/// final return value or runtime error.
-///
-/// # Examples
-/// ```rust,ignore
-/// let output = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
-/// let sink_output = std::sync::Arc::clone(&output);
-/// let sink: OutputSink = std::sync::Arc::new(move |message: String| {
-///     sink_output
-///         .lock()
-///         .expect("failed to capture script output")
-///         .push(message);
-/// });
-///
-/// run_rhai_script_with_sink("40 + 2", &sink);
-/// assert_eq!(output.lock().unwrap().as_slice(), ["42"]);
-/// ```
-fn run_rhai_script_with_sink(script: &str, sink: &OutputSink) {
-    let mut engine = configure_engine(rhai::Engine::new());
-
-    let print_sink = Arc::clone(sink);
-    engine.on_print(move |x| {
-        print_sink(x.to_string());
-    });
+/// Requests that the script currently running against the managed engine
+/// stop at its next progress check.
+#[tauri::command]
+fn rhai_cancel(window: tauri::Window) {
+    let app_state = {
+        let state = window.state::<Arc<MyState>>();
+        Arc::clone(&state)
+    };
 
-    match engine.compile(script) {
-        Ok(script_ast) => match engine.eval_ast::<rhai::Dynamic>(&script_ast) {
-            Ok(result) => sink(result.to_string()),
-            Err(e) => sink(format!("{:?}", e)),
-        },
-        Err(e) => sink(e.to_string()),
-    }
+    app_state.cancel.store(true, Ordering::Relaxed);
 }
 
-#[cfg(test)]
-mod tests {
-    use super::{append_output_script, run_rhai_script_with_sink, OutputSink};
-    use std::sync::{Arc, Mutex};
+#[tauri::command]
+fn rhai_script(script: &str, window: tauri::Window) {
+    let app_state = {
+        let state = window.state::<Arc<MyState>>();
+        Arc::clone(&state)
+    };
 
-    fn run_script_with_collector(script: &str) -> Vec<String> {
-        let captured_output: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
-        let sink_target = Arc::clone(&captured_output);
-        let sink: OutputSink = Arc::new(move |message: String| {
-            sink_target
-                .lock()
-                .expect("collector mutex poisoned")
-                .push(message);
-        });
+    eval_in_managed_scope(script, &window, &app_state);
+}
 
-        run_rhai_script_with_sink(script, &sink);
+/// Clears the managed REPL scope and rebuilds the managed engine (keeping the
+/// current `import` base path, if one is set).
+#[tauri::command]
+fn rhai_reset(window: tauri::Window) {
+    let app_state = {
+        let state = window.state::<Arc<MyState>>();
+        Arc::clone(&state)
+    };
 
-        let collected = captured_output
-            .lock()
-            .expect("collector mutex poisoned")
-            .clone();
+    let base_path = app_state.base_path.lock().unwrap().clone();
+    let engine = configure_engine_with_limits(
+        rhai::Engine::new(),
+        base_path,
+        None,
+        Arc::clone(&app_state.cancel),
+    );
+    *app_state.engine.lock().unwrap() = engine;
+    app_state.scope.lock().unwrap().clear();
+    app_state.cancel.store(false, Ordering::Relaxed);
+}
 
-        collected
-    }
+/// Streams each variable currently bound in the managed REPL scope as a
+/// `name: type = value` line.
+#[tauri::command]
+fn rhai_list_vars(window: tauri::Window) {
+    let app_state = {
+        let state = window.state::<Arc<MyState>>();
+        Arc::clone(&state)
+    };
 
-    #[test]
-    fn append_output_script_serializes_control_characters() {
-        let result = append_output_script("Line 1\nLine 2\tTabbed")
-            .expect("failed to serialize message with control characters");
-        assert_eq!(result, "append_output(\"Line 1\\nLine 2\\tTabbed\")");
+    let scope = app_state.scope.lock().unwrap();
+    if scope.is_empty() {
+        send_output(&window, "(no variables defined)");
+        return;
     }
 
-    #[test]
-    fn append_output_script_preserves_quotes_and_unicode() {
-        let result = append_output_script("He said, \"hi\" ☃")
-            .expect("failed to serialize message with quotes and unicode");
-        assert_eq!(result, "append_output(\"He said, \\\"hi\\\" ☃\")");
+    for (name, _, value) in scope.iter() {
+        send_output(&window, &format!("{name}: {} = {value}", value.type_name()));
     }
+}
 
-    #[test]
-    fn invalid_script_reports_parse_error() {
-        let output = run_script_with_collector("let x = ;");
-        let last_message = output
-            .last()
-            .expect("missing output entry for invalid script");
-
-        assert!(
-            last_message.contains("Unexpected"),
-            "expected parse error message, got: {last_message}",
-        );
-    }
+/// Runs every zero-parameter `test_`-prefixed function in `script`, streaming
+/// a PASS/FAIL line per test plus a final summary to the window's output pane.
+///
+/// Uses its own, freshly-created cancel flag rather than the REPL's shared
+/// `MyState::cancel`: a stale `rhai_cancel` click aimed at an unrelated REPL
+/// hang must not silently abort every test run that follows it.
+#[tauri::command]
+fn rhai_run_tests(script: &str, window: tauri::Window) {
+    let sink_window = window;
+    let output_sink: OutputSink = Arc::new(move |message: String| {
+        send_output(&sink_window, &message);
+    });
 
-    #[test]
-    fn valid_script_reports_result() {
-        let output = run_script_with_collector("40 + 2");
-        assert!(
-            output.contains(&"42".to_string()),
-            "expected valid script to produce \"42\" but saw {output:?}",
-        );
-    }
+    let cancel = Arc::new(AtomicBool::new(false));
+    run_rhai_tests_with_sink_cancellable(script, &output_sink, cancel, None);
+}
 
-    #[test]
-    fn runtime_errors_are_forwarded_to_the_sink() {
-        let output = run_script_with_collector(
-            r#"
-            fn explode() { throw("boom"); }
-            explode();
-            "#,
-        );
-
-        let last_message = output
-            .last()
-            .expect("missing output entry for runtime error");
-        assert!(
-            last_message.contains("boom"),
-            "expected runtime error payload, got: {last_message}",
-        );
-    }
+/// Starts watching `path`, streaming its output (and reruns on change) to the
+/// window. Replaces any existing watch already registered for `path`.
+///
+/// Uses its own, freshly-created cancel flag rather than the REPL's shared
+/// `MyState::cancel`: that flag never resets itself between watch reruns, so
+/// sharing it would let one stale `rhai_cancel` click permanently break
+/// live-reload for every watched file.
+#[tauri::command]
+fn rhai_watch(path: String, window: tauri::Window) {
+    let app_state = {
+        let state = window.state::<Arc<MyState>>();
+        Arc::clone(&state)
+    };
 
-    #[test]
-    fn print_statements_are_captured_before_results() {
-        let output = run_script_with_collector(r#"print("hi"); 41 + 1;"#);
-        assert_eq!(
-            output,
-            vec!["hi".to_string(), "42".to_string()],
-            "expected print output to precede the result"
-        );
+    let sink_window = window.clone();
+    let sink: OutputSink = Arc::new(move |message: String| {
+        send_output(&sink_window, &message);
+    });
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    match watch_rhai_file(PathBuf::from(&path), sink, cancel, None) {
+        Ok(handle) => {
+            if let Some(previous) = app_state
+                .watches
+                .lock()
+                .unwrap()
+                .insert(path.clone(), handle)
+            {
+                previous.stop();
+            }
+        }
+        Err(e) => send_output(&window, &format!("failed to watch {path}: {e}")),
     }
+}
+
+/// Stops watching `path`, if it is currently being watched.
+#[tauri::command]
+fn rhai_unwatch(path: String, window: tauri::Window) {
+    let app_state = {
+        let state = window.state::<Arc<MyState>>();
+        Arc::clone(&state)
+    };
 
-    #[test]
-    fn bundled_modules_are_available_under_namespaces() {
-        let output = run_script_with_collector(
-            r#"
-            let value = rand::rand(0, 10);
-            value >= 0 && value <= 10
-            "#,
-        );
-
-        let last_message = output
-            .last()
-            .expect("missing output entry for bundled module test");
-
-        assert_eq!(
-            last_message, "true",
-            "expected namespace-qualified module call to succeed"
-        );
+    if let Some(handle) = app_state.watches.lock().unwrap().remove(&path) {
+        handle.stop();
     }
 }
+
+#[tauri::command]
+fn rhai_repl(script: &str, window: tauri::Window) {
+    let app_state = {
+        let app_handle = window.app_handle();
+        let state = app_handle.state::<Arc<MyState>>();
+        Arc::clone(&state)
+    };
+
+    eval_in_managed_scope(script, &window, &app_state);
+}